@@ -0,0 +1,105 @@
+//! API for using an integrated timer to measure the frequency and duty cycle of an input signal
+
+use crate::pwm::Pins;
+use crate::rcc::{Clocks, Rcc};
+use crate::time::Hertz;
+
+/// PWM input driver, built on the same advanced timers as [`Qei`](crate::qei::Qei)
+pub struct PwmInput<TIMER> {
+    timer: TIMER,
+}
+
+macro_rules! pwm_input {
+    ($($TIM: ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apbenr:ident, $apbrstr:ident, $width:ident),)+) => {
+        $(
+            use crate::pac::$TIM;
+            impl PwmInput<$TIM> {
+                /// Configures a TIM peripheral to measure the frequency and duty cycle of the
+                /// signal presented on channel 1
+                pub fn $tim<P, PINS>(tim: $TIM, _pins: PINS, rcc: &mut Rcc) -> Self
+                where
+                    PINS: Pins<$TIM, P>,
+                {
+                    assert!(PINS::C1, "PwmInput requires a channel 1 (TI1) pin");
+
+                    // enable and reset peripherals to a clean slate state
+                    rcc.regs.$apbenr.modify(|_, w| w.$timXen().set_bit());
+                    rcc.regs.$apbrstr.modify(|_, w| w.$timXrst().set_bit());
+                    rcc.regs.$apbrstr.modify(|_, w| w.$timXrst().clear_bit());
+
+                    // CC1 captures TI1 directly (period), CC2 captures TI1 indirectly (duty)
+                    tim.ccmr1_input().modify(|_, w| w
+                        .cc1s().ti1()
+                        .cc2s().ti1()
+                    );
+                    // CC1 on the rising edge, CC2 on the falling edge of the same signal
+                    tim.ccer.write(|w| w
+                        .cc1p().clear_bit()
+                        .cc2p().set_bit()
+                    );
+                    // reset the counter on every TI1FP1 rising edge
+                    tim.smcr.write(|w| w
+                        .ts().ti1fp1()
+                        .sms().reset_mode()
+                    );
+
+                    tim.arr.write(|w| w.arr().variant($width::MAX));
+                    tim.ccer.modify(|_, w| w
+                        .cc1e().set_bit()
+                        .cc2e().set_bit()
+                    );
+                    tim.cr1.write(|w| w.cen().set_bit());
+
+                    Self {
+                        timer: tim,
+                    }
+                }
+
+                /// Measure the frequency of the input signal from the period captured on CC1
+                pub fn read_frequency(&self, clocks: &Clocks) -> Hertz {
+                    // widen to u64 before the `+ 1` so a capture of `$width::MAX` can't overflow
+                    let period = self.timer.ccr1.read().ccr().bits() as u64 + 1;
+                    Hertz((clocks.pclk().0 as u64 / period) as u32)
+                }
+
+                /// Measure the duty cycle of the input signal as the ratio of the CC2 (high
+                /// time) and CC1 (period) captures, scaled to the full range of a `u16`
+                pub fn read_duty_cycle(&self) -> u16 {
+                    // widen to u64: `period` can't overflow on `+ 1`, and `high_time * u16::MAX`
+                    // can't overflow even when `high_time` is a full 32-bit TIM2 capture
+                    let period = self.timer.ccr1.read().ccr().bits() as u64 + 1;
+                    let high_time = self.timer.ccr2.read().ccr().bits() as u64;
+                    ((high_time * u16::MAX as u64) / period) as u16
+                }
+
+                /// Disable the timer's clock and release it, so it can be reused for another purpose
+                pub fn release(self, rcc: &mut Rcc) -> $TIM {
+                    self.timer.cr1.modify(|_, w| w.cen().clear_bit());
+                    rcc.regs.$apbenr.modify(|_, w| w.$timXen().clear_bit());
+                    self.timer
+                }
+            }
+        )+
+    }
+}
+
+pwm_input! {
+    TIM3: (tim3, tim3en, tim3rst, apb1enr, apb1rstr, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f031",
+    feature = "stm32f038",
+    feature = "stm32f042",
+    feature = "stm32f048",
+    feature = "stm32f051",
+    feature = "stm32f058",
+    feature = "stm32f071",
+    feature = "stm32f072",
+    feature = "stm32f078",
+    feature = "stm32f091",
+    feature = "stm32f098"
+))]
+pwm_input! {
+    TIM2: (tim2, tim2en, tim2rst, apb1enr, apb1rstr, u32),
+}
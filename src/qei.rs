@@ -19,13 +19,46 @@ pub struct Qei<TIMER> {
     timer: TIMER,
 }
 
+/// Extension trait that constrains `TIM` peripherals compatible with [`Qei`]
+pub trait QeiExt<TIM> {
+    /// The width of the timer backing this `Qei` instance
+    type Count;
+
+    /// Configures a TIM peripheral as a quadrature encoder
+    fn qei<P, PINS>(self, pins: PINS, rcc: &mut Rcc, options: QeiOptions<Self::Count>) -> Qei<TIM>
+    where
+        PINS: Pins<TIM, P>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The polarity applied to a channel's input capture
+pub enum Polarity {
+    /// Capture on the rising edge
+    Rising,
+    /// Capture on the falling edge
+    Falling,
+}
+
+/// Configuration for the `Qei` peripheral's input filter, channel polarities, and count range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QeiOptions<COUNT> {
+    /// Digital input filter, 0 (no filtering) to 15 (maximum filtering)
+    pub filter: u8,
+    /// Polarity applied to channel 1's input capture
+    pub ic1_polarity: Polarity,
+    /// Polarity applied to channel 2's input capture
+    pub ic2_polarity: Polarity,
+    /// Value written to `ARR`
+    pub auto_reload_value: COUNT,
+}
+
 macro_rules! qei {
     ($($TIM: ident: ($tim:ident, $timXen:ident, $timXrst:ident, $apbenr:ident, $apbrstr:ident, $width:ident),)+) => {
         $(
             use crate::pac::$TIM;
             impl Qei<$TIM> {
                 /// Configures a TIM peripheral as a quadrature encoder
-                pub fn $tim<P, PINS>(tim: $TIM, _pins: PINS, rcc: &mut Rcc) -> Self
+                pub fn $tim<P, PINS>(tim: $TIM, _pins: PINS, rcc: &mut Rcc, options: QeiOptions<$width>) -> Self
                 where
                     PINS: Pins<$TIM, P>,
                 {
@@ -34,27 +67,38 @@ macro_rules! qei {
                     rcc.regs.$apbrstr.modify(|_, w| w.$timXrst().set_bit());
                     rcc.regs.$apbrstr.modify(|_, w| w.$timXrst().clear_bit());
 
+                    let ic1p = options.ic1_polarity == Polarity::Falling;
+                    let ic2p = options.ic2_polarity == Polarity::Falling;
+
                     if PINS::C1 && PINS::C2 {
                         tim.ccmr1_input().modify(|_, w| w
                             .cc1s().ti1()
                             .cc2s().ti2()
+                            .ic1f().bits(options.filter)
+                            .ic2f().bits(options.filter)
                         );
                         tim.ccer.write(|w| w
-                            .cc1p().set_bit()
-                            .cc2p().set_bit()
+                            .cc1p().bit(ic1p)
+                            .cc2p().bit(ic2p)
                         );
                         tim.smcr.write(|w| w.sms().encoder_mode_3());
                     } else if PINS::C1 {
-                        tim.ccmr1_input().modify(|_, w| w.cc1s().ti1());
-                        tim.ccer.write(|w| w.cc1p().set_bit());
+                        tim.ccmr1_input().modify(|_, w| w
+                            .cc1s().ti1()
+                            .ic1f().bits(options.filter)
+                        );
+                        tim.ccer.write(|w| w.cc1p().bit(ic1p));
                         tim.smcr.write(|w| w.sms().encoder_mode_1());
                     } else if PINS::C2 {
-                        tim.ccmr1_input().modify(|_, w| w.cc2s().ti2());
-                        tim.ccer.write(|w| w.cc2p().set_bit());
+                        tim.ccmr1_input().modify(|_, w| w
+                            .cc2s().ti2()
+                            .ic2f().bits(options.filter)
+                        );
+                        tim.ccer.write(|w| w.cc2p().bit(ic2p));
                         tim.smcr.write(|w| w.sms().encoder_mode_2());
                     }
 
-                    tim.arr.write(|w| w.arr().variant($width::MAX));
+                    tim.arr.write(|w| w.arr().variant(options.auto_reload_value));
                     tim.cr1.write(|w| w.cen().set_bit());
 
                     Self {
@@ -74,6 +118,61 @@ macro_rules! qei {
                 pub fn count(&self) -> $width {
                     self.timer.cnt.read().cnt().bits()
                 }
+
+                /// Set the current count of the encoder
+                pub fn set_count(&mut self, value: $width) {
+                    self.timer.cnt.write(|w| w.cnt().variant(value));
+                }
+
+                /// Reset the count of the encoder to 0
+                pub fn reset(&mut self) {
+                    self.set_count(0);
+                }
+
+                /// Disable the timer's clock and release it, so it can be reused for another purpose
+                pub fn release(self, rcc: &mut Rcc) -> $TIM {
+                    self.timer.cr1.modify(|_, w| w.cen().clear_bit());
+                    rcc.regs.$apbenr.modify(|_, w| w.$timXen().clear_bit());
+                    self.timer
+                }
+            }
+
+            impl embedded_hal::Qei for Qei<$TIM> {
+                type Count = $width;
+
+                fn count(&self) -> Self::Count {
+                    self.count()
+                }
+
+                fn direction(&self) -> embedded_hal::Direction {
+                    match self.read_direction() {
+                        Direction::Upcounting => embedded_hal::Direction::Upcounting,
+                        Direction::Downcounting => embedded_hal::Direction::Downcounting,
+                    }
+                }
+            }
+
+            impl QeiExt<$TIM> for $TIM {
+                type Count = $width;
+
+                fn qei<P, PINS>(self, pins: PINS, rcc: &mut Rcc, options: QeiOptions<$width>) -> Qei<$TIM>
+                where
+                    PINS: Pins<$TIM, P>,
+                {
+                    Qei::$tim(self, pins, rcc, options)
+                }
+            }
+
+            impl Default for QeiOptions<$width> {
+                /// No filtering, falling-edge polarity on both channels, `ARR = $width::MAX`
+                fn default() -> Self {
+                    Self {
+                        filter: 0,
+                        ic1_polarity: Polarity::Falling,
+                        ic2_polarity: Polarity::Falling,
+                        auto_reload_value: $width::MAX,
+                    }
+                }
             }
         )+
     }
@@ -83,6 +182,61 @@ qei! {
     TIM3: (tim3, tim3en, tim3rst, apb1enr, apb1rstr, u16),
 }
 
+use core::sync::atomic::{AtomicI32, Ordering};
+
+/// Software-extended 32-bit counter for a 16-bit `Qei<TIM3>`
+pub struct QeiCount32 {
+    qei: Qei<TIM3>,
+    high: AtomicI32,
+}
+
+impl QeiCount32 {
+    /// Wrap a `Qei<TIM3>` with a software-maintained high word, initialized to 0
+    pub fn new(qei: Qei<TIM3>) -> Self {
+        Self {
+            qei,
+            high: AtomicI32::new(0),
+        }
+    }
+
+    /// Enable the timer's update interrupt, so `on_overflow` is driven by the user's ISR
+    pub fn enable_interrupt(&mut self) {
+        self.qei.timer.dier.modify(|_, w| w.uie().set_bit());
+    }
+
+    /// Handle a timer update event; call this from the TIM3 interrupt handler. Clears `SR.UIF`.
+    pub fn on_overflow(&self) {
+        match self.qei.read_direction() {
+            Direction::Upcounting => self.high.fetch_add(1, Ordering::Relaxed),
+            Direction::Downcounting => self.high.fetch_sub(1, Ordering::Relaxed),
+        };
+        self.qei.timer.sr.modify(|_, w| w.uif().clear_bit());
+    }
+
+    /// Read the full 32-bit-extended count
+    pub fn count_64(&self) -> i64 {
+        let modulus = self.qei.timer.arr.read().arr().bits() as i64 + 1;
+
+        loop {
+            let high_before = self.high.load(Ordering::Relaxed);
+            let low = self.qei.count();
+            let high_after = self.high.load(Ordering::Relaxed);
+
+            if high_before == high_after {
+                return (high_before as i64) * modulus + (low as i64);
+            }
+        }
+    }
+
+    /// Disable the update interrupt and release the underlying `Qei`, discarding the
+    /// software-maintained high word
+    pub fn release(self) -> Qei<TIM3> {
+        self.qei.timer.dier.modify(|_, w| w.uie().clear_bit());
+        self.qei.timer.sr.modify(|_, w| w.uif().clear_bit());
+        self.qei
+    }
+}
+
 #[cfg(any(
     feature = "stm32f031",
     feature = "stm32f038",